@@ -1,28 +1,366 @@
-use std::env::consts::ARCH;
+use std::env::consts::{ARCH, OS};
 use std::ffi::OsString;
-use std::{any::Any, path::PathBuf};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::{any::Any, path::Path, path::PathBuf};
 
 use anyhow::{anyhow, Result};
 use async_compression::futures::bufread::GzipDecoder;
 use async_tar::Archive;
 use async_trait::async_trait;
 use futures::io::BufReader;
-use log::info;
+use log::{info, warn};
 use regex::Regex;
+use sha2::{Digest, Sha256};
 
 use language::{LanguageServerName, LspAdapter, LspAdapterDelegate};
 use lsp::LanguageServerBinary;
 use smol::io::AsyncReadExt;
 
 const JDT_MILESTONES_URL: &str = "https://download.eclipse.org";
-const JRE_21_MACOS_AARCH64: &'static str =
-    "https://corretto.aws/downloads/latest/amazon-corretto-21-aarch64-macos-jdk.tar.gz";
-const JRE_21_MACOS_X64: &'static str =
-    "https://corretto.aws/downloads/latest/amazon-corretto-21-x64-macos-jdk.tar.gz";
 
-const JAVA_HOME: &'static str = "amazon-corretto-21.jdk/Contents/Home";
+/// The JRE feature version JDT-LS is provisioned with. JDT-LS itself only
+/// needs a runtime to execute on; it doesn't need to match the project's
+/// source/target Java version.
+const JDK_FEATURE_VERSION: u32 = 21;
 
-pub struct JavaLspAdapter {}
+/// The minimum JVM major version JDT-LS itself needs to execute. This is
+/// independent of the project's requested Java version (see
+/// `JDK_FEATURE_VERSION` above): a system JDK that only satisfies a project
+/// pinned to, say, Java 8 or 11 cannot launch the server itself.
+const JDT_LS_MIN_RUNTIME_VERSION: u32 = 17;
+
+#[derive(Clone, Copy)]
+enum ArchiveKind {
+    TarGz,
+    Zip,
+}
+
+/// The JDK vendor to provision JDT-LS's runtime from, mirroring the set of
+/// distributions `actions/setup-java` supports. Resolved from the `java`
+/// language setting, defaulting to `Corretto` to preserve prior behavior.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JdkDistribution {
+    #[default]
+    Corretto,
+    Temurin,
+    Zulu,
+    GraalVm,
+}
+
+/// Parses the `languages.Java` block of a `.zed/settings.json` document,
+/// e.g.:
+/// ```json
+/// { "languages": { "Java": {
+///     "jdk_distribution": "temurin",
+///     "import": { "gradle": true, "maven": true },
+///     "format_on_save": true,
+///     "null_analysis_mode": "automatic"
+/// } } }
+/// ```
+/// Any field that's missing, or the whole document if it's missing/invalid,
+/// falls back to `defaults`.
+fn parse_java_language_settings(
+    contents: &str,
+    defaults: ConfiguredJavaSettings,
+) -> ConfiguredJavaSettings {
+    let Ok(settings) = serde_json::from_str::<serde_json::Value>(contents) else {
+        return defaults;
+    };
+    let Some(java) = settings.get("languages").and_then(|l| l.get("Java")) else {
+        return defaults;
+    };
+
+    let configured_jdk_distribution = java.get("jdk_distribution").and_then(|v| v.as_str());
+
+    ConfiguredJavaSettings {
+        jdk_distribution: configured_jdk_distribution
+            .map(JdkDistribution::from_setting)
+            .unwrap_or(defaults.jdk_distribution),
+        jdk_distribution_explicit: configured_jdk_distribution.is_some(),
+        gradle_enabled: java
+            .get("import")
+            .and_then(|v| v.get("gradle"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.gradle_enabled),
+        maven_enabled: java
+            .get("import")
+            .and_then(|v| v.get("maven"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.maven_enabled),
+        format_on_save: java
+            .get("format_on_save")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.format_on_save),
+        null_analysis_mode: java
+            .get("null_analysis_mode")
+            .and_then(|v| v.as_str())
+            .map(|mode| mode.to_owned())
+            .unwrap_or(defaults.null_analysis_mode),
+    }
+}
+
+impl JdkDistribution {
+    pub fn from_setting(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "temurin" | "adoptium" => Self::Temurin,
+            "zulu" => Self::Zulu,
+            "graalvm" => Self::GraalVm,
+            _ => Self::Corretto,
+        }
+    }
+
+    /// Builds the download URL for this distribution's JRE, or `None` if it
+    /// doesn't publish a build for the given `(os, arch)`.
+    fn jre_download(
+        &self,
+        os: &str,
+        arch: &str,
+        feature_version: u32,
+    ) -> Option<(String, ArchiveKind)> {
+        match self {
+            Self::Corretto => corretto_download(os, arch, feature_version),
+            Self::Temurin => temurin_download(os, arch, feature_version),
+            Self::Zulu => zulu_download(os, arch, feature_version),
+            Self::GraalVm => graalvm_download(os, arch, feature_version),
+        }
+    }
+
+    /// Resolves the expected SHA-256 digest for this distribution's JRE
+    /// download, or `None` if this distribution doesn't publish one at a
+    /// known stable URL. Corretto's `latest/` artifacts have a matching
+    /// `latest_checksum/` manifest; Temurin's binary-redirect endpoint
+    /// doesn't, but Adoptium's assets API reports each asset's checksum
+    /// directly, so that's queried instead.
+    async fn checksum(
+        &self,
+        delegate: &dyn LspAdapterDelegate,
+        download_url: &str,
+        os: &str,
+        arch: &str,
+        feature_version: u32,
+    ) -> Result<Option<String>> {
+        match self {
+            Self::Corretto => {
+                let checksum_url = checksum_url_for(download_url);
+                let mut response = String::new();
+                delegate
+                    .http_client()
+                    .get(&checksum_url, Default::default(), true)
+                    .await
+                    .map_err(|err| anyhow!("error downloading JRE checksum: {}", err))?
+                    .body_mut()
+                    .read_to_string(&mut response)
+                    .await?;
+                Ok(Some(
+                    parse_sha256_file(&response)
+                        .ok_or_else(|| anyhow!("no checksum found at {}", checksum_url))?
+                        .to_owned(),
+                ))
+            }
+            Self::Temurin => {
+                // Adoptium names these `mac`/`x64`, not Rust's `macos`/`x86_64`.
+                let os = if os == "macos" { "mac" } else { os };
+                let arch = if arch == "x86_64" { "x64" } else { arch };
+                let assets_url = temurin_assets_api_url(feature_version);
+                let mut response = String::new();
+                delegate
+                    .http_client()
+                    .get(&assets_url, Default::default(), true)
+                    .await
+                    .map_err(|err| anyhow!("error downloading Temurin asset metadata: {}", err))?
+                    .body_mut()
+                    .read_to_string(&mut response)
+                    .await?;
+                let checksum =
+                    parse_temurin_assets_checksum(&response, os, arch).ok_or_else(|| {
+                        anyhow!(
+                            "no checksum found in Temurin asset metadata at {} for {} {}",
+                            assets_url,
+                            os,
+                            arch
+                        )
+                    })?;
+                Ok(Some(checksum))
+            }
+            Self::Zulu | Self::GraalVm => {
+                warn!(
+                    "{:?} does not publish a checksum at a known stable URL; installing its JRE \
+                     without integrity verification",
+                    self
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// The prefix of the top-level directory name each vendor's archive
+    /// extracts into, used to detect an already-provisioned JDK. Zulu's
+    /// archives extract to e.g. `zulu21.32.17-ca-jdk21.0.2-linux_x64` -- no
+    /// hyphen directly after `zulu`.
+    fn home_dir_prefix(&self) -> &'static str {
+        match self {
+            Self::Corretto => "amazon-corretto",
+            Self::Temurin => "jdk-",
+            Self::Zulu => "zulu",
+            Self::GraalVm => "graalvm-",
+        }
+    }
+}
+
+fn corretto_download(os: &str, arch: &str, feature_version: u32) -> Option<(String, ArchiveKind)> {
+    let (os_segment, ext, kind) = match os {
+        "macos" => ("macos", "tar.gz", ArchiveKind::TarGz),
+        "linux" => ("linux", "tar.gz", ArchiveKind::TarGz),
+        "windows" => ("windows", "zip", ArchiveKind::Zip),
+        _ => return None,
+    };
+    let arch_segment = match arch {
+        "aarch64" => "aarch64",
+        "x86_64" => "x64",
+        _ => return None,
+    };
+    Some((
+        format!(
+            "https://corretto.aws/downloads/latest/amazon-corretto-{feature_version}-{arch_segment}-{os_segment}-jdk.{ext}"
+        ),
+        kind,
+    ))
+}
+
+fn temurin_download(os: &str, arch: &str, feature_version: u32) -> Option<(String, ArchiveKind)> {
+    let os_segment = match os {
+        "macos" => "mac",
+        "linux" => "linux",
+        "windows" => "windows",
+        _ => return None,
+    };
+    let arch_segment = match arch {
+        "aarch64" => "aarch64",
+        "x86_64" => "x64",
+        _ => return None,
+    };
+    let kind = if os == "windows" {
+        ArchiveKind::Zip
+    } else {
+        ArchiveKind::TarGz
+    };
+    Some((
+        format!(
+            "https://api.adoptium.net/v3/binary/latest/{feature_version}/ga/{os_segment}/{arch_segment}/jdk/hotspot/normal/eclipse"
+        ),
+        kind,
+    ))
+}
+
+fn zulu_download(os: &str, arch: &str, feature_version: u32) -> Option<(String, ArchiveKind)> {
+    let (os_segment, ext, kind) = match os {
+        "macos" => ("macosx", "tar.gz", ArchiveKind::TarGz),
+        "linux" => ("linux", "tar.gz", ArchiveKind::TarGz),
+        "windows" => ("win", "zip", ArchiveKind::Zip),
+        _ => return None,
+    };
+    let arch_segment = match arch {
+        "aarch64" => "aarch64",
+        "x86_64" => "x64",
+        _ => return None,
+    };
+    Some((
+        format!(
+            "https://cdn.azul.com/zulu/bin/zulu{feature_version}-ca-jdk{feature_version}-{os_segment}_{arch_segment}.{ext}"
+        ),
+        kind,
+    ))
+}
+
+fn graalvm_download(os: &str, arch: &str, feature_version: u32) -> Option<(String, ArchiveKind)> {
+    let (os_segment, ext, kind) = match os {
+        "macos" => ("macos", "tar.gz", ArchiveKind::TarGz),
+        "linux" => ("linux", "tar.gz", ArchiveKind::TarGz),
+        "windows" => ("windows", "zip", ArchiveKind::Zip),
+        _ => return None,
+    };
+    let arch_segment = match arch {
+        "aarch64" => "aarch64",
+        "x86_64" => "x64",
+        _ => return None,
+    };
+    Some((
+        format!(
+            "https://github.com/graalvm/graalvm-ce-builds/releases/download/jdk-{feature_version}/graalvm-community-jdk-{feature_version}_{os_segment}-{arch_segment}_bin.{ext}"
+        ),
+        kind,
+    ))
+}
+
+/// The subset of the `languages.Java` settings block in `.zed/settings.json`
+/// this adapter acts on.
+#[derive(Debug, Clone, PartialEq)]
+struct ConfiguredJavaSettings {
+    jdk_distribution: JdkDistribution,
+    /// Whether `jdk_distribution` came from an explicit `languages.Java.jdk_distribution`
+    /// setting, as opposed to this adapter's constructed default. A system JDK found on
+    /// `JAVA_HOME`/common install paths must not silently override an explicit choice here
+    /// (that's the whole point of the setting existing).
+    jdk_distribution_explicit: bool,
+    gradle_enabled: bool,
+    maven_enabled: bool,
+    format_on_save: bool,
+    null_analysis_mode: String,
+}
+
+impl ConfiguredJavaSettings {
+    fn defaults_for(distribution: JdkDistribution) -> Self {
+        Self {
+            jdk_distribution: distribution,
+            jdk_distribution_explicit: false,
+            gradle_enabled: true,
+            maven_enabled: true,
+            format_on_save: true,
+            null_analysis_mode: "automatic".to_owned(),
+        }
+    }
+}
+
+/// Everything resolved about how to run JDT-LS for a workspace, cached by
+/// `fetch_server_binary`/`cached_server_binary` (which have an
+/// `LspAdapterDelegate` to resolve it with) so `installation_test_binary` and
+/// `initialization_options` (which don't) can reuse it.
+#[derive(Debug, Clone)]
+struct ResolvedRuntime {
+    java_path: PathBuf,
+    requested_major: Option<u32>,
+    settings: ConfiguredJavaSettings,
+}
+
+#[derive(Default)]
+pub struct JavaLspAdapter {
+    distribution: JdkDistribution,
+    resolved_runtime: std::sync::Mutex<Option<ResolvedRuntime>>,
+}
+
+impl JavaLspAdapter {
+    pub fn new(distribution: JdkDistribution) -> Self {
+        Self {
+            distribution,
+            resolved_runtime: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Resolves the `languages.Java` settings for this workspace from its
+    /// `.zed/settings.json`, falling back to this adapter's constructed
+    /// distribution and this method's own defaults for anything unset.
+    async fn resolve_settings(&self, delegate: &dyn LspAdapterDelegate) -> ConfiguredJavaSettings {
+        let defaults = ConfiguredJavaSettings::defaults_for(self.distribution);
+        let Ok(contents) = delegate
+            .read_text_file(PathBuf::from(".zed/settings.json"))
+            .await
+        else {
+            return defaults;
+        };
+        parse_java_language_settings(&contents, defaults)
+    }
+}
 
 #[async_trait]
 impl LspAdapter for JavaLspAdapter {
@@ -76,28 +414,72 @@ impl LspAdapter for JavaLspAdapter {
         info!("fetch_server_binary");
         let jdtls_version = version.downcast::<String>().unwrap();
 
-        let jre21_url: &str = match ARCH {
-            "aarch64" => JRE_21_MACOS_AARCH64,
-            "x86_64" => JRE_21_MACOS_X64,
-            _ => "", // meh
+        // `requested_major` is the project's own target version (from
+        // `.java-version`/`.tool-versions`); it only feeds `java.configuration.runtimes`
+        // below, never the JVM that launches the server itself -- that must satisfy
+        // `JDT_LS_MIN_RUNTIME_VERSION` regardless of what the project targets.
+        let requested_major = discover_requested_java_version(delegate).await;
+        let settings = self.resolve_settings(delegate).await;
+        let distribution = settings.jdk_distribution;
+        let system_jdk = if settings.jdk_distribution_explicit {
+            None
+        } else {
+            discover_system_jdk(JDT_LS_MIN_RUNTIME_VERSION)
         };
+        if let Some(system_jdk) = &system_jdk {
+            info!(
+                "Found a system JDK at {:?} that can run JDT-LS (project requests major: {:?}); skipping JRE provisioning",
+                system_jdk, requested_major
+            );
+        } else if settings.jdk_distribution_explicit {
+            info!(
+                "languages.Java.jdk_distribution is explicitly set to {:?}; skipping system JDK discovery so that setting isn't silently overridden",
+                distribution
+            );
+        }
+
+        if system_jdk.is_none() && jdk_root(&container_dir, distribution).is_none() {
+            let (jre21_url, archive_kind) = distribution
+                .jre_download(OS, ARCH, JDK_FEATURE_VERSION)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "no JRE download known for {:?} on {} {}",
+                        distribution,
+                        OS,
+                        ARCH
+                    )
+                })?;
+
+            let expected_sha256 = distribution
+                .checksum(delegate, &jre21_url, OS, ARCH, JDK_FEATURE_VERSION)
+                .await?;
 
-        if !container_dir.join(JAVA_HOME).exists() {
             info!("Downloading {}", jre21_url);
-            let mut response = delegate
-                .http_client()
-                .get(jre21_url, Default::default(), true)
-                .await
-                .map_err(|err| anyhow!("error downloading JRE-21: {}", err))?;
-            let decompressed_bytes = GzipDecoder::new(BufReader::new(response.body_mut()));
-            let archive = Archive::new(decompressed_bytes);
-            archive.unpack(container_dir.clone()).await?;
+            let archive_path = container_dir.join("jre-download.tmp");
+            download_to_file(
+                delegate,
+                &jre21_url,
+                &archive_path,
+                expected_sha256.as_deref(),
+            )
+            .await
+            .map_err(|err| anyhow!("JRE download failed: {}", err))?;
+
+            match archive_kind {
+                ArchiveKind::TarGz => {
+                    let file = smol::fs::File::open(&archive_path).await?;
+                    let decompressed_bytes = GzipDecoder::new(BufReader::new(file));
+                    let archive = Archive::new(decompressed_bytes);
+                    archive.unpack(container_dir.clone()).await?;
+                }
+                ArchiveKind::Zip => {
+                    unpack_zip(&archive_path, &container_dir)?;
+                }
+            }
+            std::fs::remove_file(&archive_path).ok();
         }
 
-        if !container_dir
-            .join("plugins/org.eclipse.equinox.launcher_1.6.700.v20231214-2017.jar")
-            .exists()
-        {
+        if find_equinox_launcher_jar(&container_dir).is_none() {
             let version_page_url =
                 format!("{}/jdtls/milestones/{}", JDT_MILESTONES_URL, jdtls_version);
 
@@ -122,24 +504,53 @@ impl LspAdapter for JavaLspAdapter {
                 .as_str();
 
             let download_url = format!("{}{}", JDT_MILESTONES_URL, build);
-            info!("Downloading the JDT-LS from {}", download_url);
-            let mut response = delegate
+
+            let checksum_url = format!("{}.sha256", download_url);
+            let mut checksum_response = String::new();
+            delegate
                 .http_client()
-                .get(&download_url, Default::default(), true)
+                .get(&checksum_url, Default::default(), true)
                 .await
-                .map_err(|err| anyhow!("error downloading release: {}", err))?;
-            let decompressed_bytes = GzipDecoder::new(BufReader::new(response.body_mut()));
+                .map_err(|err| anyhow!("error downloading JDT-LS checksum: {}", err))?
+                .body_mut()
+                .read_to_string(&mut checksum_response)
+                .await?;
+            let expected_sha256 = parse_sha256_file(&checksum_response)
+                .ok_or_else(|| anyhow!("no checksum found at {}", checksum_url))?
+                .to_owned();
+
+            info!("Downloading the JDT-LS from {}", download_url);
+            let archive_path = container_dir.join("jdtls-download.tmp");
+            download_to_file(
+                delegate,
+                &download_url,
+                &archive_path,
+                Some(&expected_sha256),
+            )
+            .await
+            .map_err(|err| anyhow!("JDT-LS download failed: {}", err))?;
+
+            let file = smol::fs::File::open(&archive_path).await?;
+            let decompressed_bytes = GzipDecoder::new(BufReader::new(file));
             let archive = Archive::new(decompressed_bytes);
             archive.unpack(container_dir.clone()).await?;
+            std::fs::remove_file(&archive_path).ok();
 
             info!("{:?}", container_dir.join("version.txt"));
             std::fs::write(container_dir.join("version.txt"), &*jdtls_version)?;
         }
 
-        let arguments = arguments(&container_dir);
+        let java_path = system_jdk.unwrap_or_else(|| java(&container_dir, distribution));
+        *self.resolved_runtime.lock().unwrap() = Some(ResolvedRuntime {
+            java_path: java_path.clone(),
+            requested_major,
+            settings,
+        });
+        let data_dir = workspace_data_dir(&container_dir, delegate.worktree_root_path());
+        std::fs::create_dir_all(&data_dir)?;
         Ok(LanguageServerBinary {
-            path: java(&container_dir),
-            arguments,
+            path: java_path,
+            arguments: arguments(&container_dir, &data_dir),
             env: None,
         })
     }
@@ -147,13 +558,33 @@ impl LspAdapter for JavaLspAdapter {
     async fn cached_server_binary(
         &self,
         container_dir: PathBuf,
-        _: &dyn LspAdapterDelegate,
+        delegate: &dyn LspAdapterDelegate,
     ) -> Option<LanguageServerBinary> {
         info!("cached_server_binary");
 
+        let requested_major = discover_requested_java_version(delegate).await;
+        let settings = self.resolve_settings(delegate).await;
+        let java_path = if settings.jdk_distribution_explicit {
+            info!(
+                "languages.Java.jdk_distribution is explicitly set to {:?}; skipping system JDK discovery so that setting isn't silently overridden",
+                settings.jdk_distribution
+            );
+            None
+        } else {
+            discover_system_jdk(JDT_LS_MIN_RUNTIME_VERSION)
+        }
+        .unwrap_or_else(|| java(&container_dir, settings.jdk_distribution));
+        *self.resolved_runtime.lock().unwrap() = Some(ResolvedRuntime {
+            java_path: java_path.clone(),
+            requested_major,
+            settings,
+        });
+
+        let data_dir = workspace_data_dir(&container_dir, delegate.worktree_root_path());
+        std::fs::create_dir_all(&data_dir).ok()?;
         Some(LanguageServerBinary {
-            path: java(&container_dir),
-            arguments: arguments(&container_dir),
+            path: java_path,
+            arguments: arguments(&container_dir, &data_dir),
             env: None,
         })
     }
@@ -163,25 +594,370 @@ impl LspAdapter for JavaLspAdapter {
         container_dir: PathBuf,
     ) -> Option<LanguageServerBinary> {
         info!("installation_test_binary");
+        // No `LspAdapterDelegate` here to read the workspace settings with, so
+        // reuse whatever `fetch_server_binary`/`cached_server_binary` already
+        // resolved; if neither of those has run yet in this process, use a
+        // system JDK that can run JDT-LS, else the provisioned JRE under
+        // `container_dir`.
+        let java_path = self
+            .resolved_runtime
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|resolved| resolved.java_path.clone())
+            .or_else(|| discover_system_jdk(JDT_LS_MIN_RUNTIME_VERSION))
+            .unwrap_or_else(|| java(&container_dir, self.distribution));
+
+        // No workspace to key a data directory by here; use a fixed one scoped
+        // to this container_dir, which is itself per-language-server-instance.
+        let data_dir = container_dir.join("data_test");
+        std::fs::create_dir_all(&data_dir).ok()?;
         Some(LanguageServerBinary {
-            path: java(&container_dir),
-            arguments: arguments(&container_dir),
+            path: java_path,
+            arguments: arguments(&container_dir, &data_dir),
             env: None,
         })
     }
 
     fn initialization_options(&self) -> Option<serde_json::Value> {
-        None
+        // `fetch_server_binary`/`cached_server_binary` always run before JDT-LS is
+        // actually started, so by the time this is called `resolved_runtime` is
+        // populated; we still fall back to this adapter's constructed defaults for
+        // the rare case neither has run yet, just without a `path` for `runtimes`.
+        let resolved = self.resolved_runtime.lock().unwrap().clone();
+        let settings = resolved
+            .as_ref()
+            .map(|resolved| resolved.settings.clone())
+            .unwrap_or_else(|| ConfiguredJavaSettings::defaults_for(self.distribution));
+
+        let mut runtime = serde_json::json!({
+            "name": format!("JavaSE-{}", JDK_FEATURE_VERSION),
+            "default": true,
+        });
+        let mut runtimes = vec![];
+        if let Some(resolved) = &resolved {
+            runtime["path"] = resolved.java_path.to_string_lossy().into_owned().into();
+            runtimes.push(runtime);
+
+            // If the project targets a different version than the runtime JDT-LS
+            // itself launches with, also tell it about that so it can attribute
+            // project sources to the right `JavaSE-<N>` execution environment; we
+            // don't have a separate JDK discovered for it, so it's pathless and
+            // non-default.
+            if let Some(requested_major) = resolved.requested_major {
+                if requested_major != JDK_FEATURE_VERSION {
+                    runtimes.push(serde_json::json!({
+                        "name": format!("JavaSE-{}", requested_major),
+                        "default": false,
+                    }));
+                }
+            }
+        } else {
+            runtimes.push(runtime);
+        }
+
+        Some(serde_json::json!({
+            "extendedClientCapabilities": {
+                "classFileContentsSupport": true,
+                "generateToStringPromptSupport": true,
+                "advancedOrganizeImportsSupport": true,
+                "advancedGenerateAccessorsSupport": true,
+                "advancedExtractRefactoringSupport": true,
+                "resolveAdditionalTextEditsSupport": true,
+            },
+            "settings": {
+                "java": {
+                    "configuration": {
+                        "updateBuildConfiguration": "interactive",
+                        "runtimes": runtimes,
+                    },
+                    "import": {
+                        "gradle": { "enabled": settings.gradle_enabled },
+                        "maven": { "enabled": settings.maven_enabled },
+                    },
+                    "format": {
+                        "enabled": settings.format_on_save,
+                    },
+                    "compiler": {
+                        "nullAnalysis": { "mode": settings.null_analysis_mode },
+                    },
+                },
+            },
+        }))
+    }
+}
+
+/// Reads the Java major version a project requires from the files
+/// `setup-java` itself consults: `.java-version` (e.g. `21` or `1.8`) and
+/// `.tool-versions` (e.g. a `java corretto-21.0.2` line).
+async fn discover_requested_java_version(delegate: &dyn LspAdapterDelegate) -> Option<u32> {
+    if let Ok(contents) = delegate
+        .read_text_file(PathBuf::from(".java-version"))
+        .await
+    {
+        if let Some(major) = parse_java_version_file(&contents) {
+            return Some(major);
+        }
+    }
+    if let Ok(contents) = delegate
+        .read_text_file(PathBuf::from(".tool-versions"))
+        .await
+    {
+        if let Some(major) = parse_tool_versions_file(&contents) {
+            return Some(major);
+        }
     }
+    None
 }
 
-fn java(container_dir: &PathBuf) -> PathBuf {
-    PathBuf::from(container_dir.join("amazon-corretto-21.jdk/Contents/Home/bin/java"))
+/// Parses `.java-version` contents (`21`, `1.8`) into a major version.
+fn parse_java_version_file(contents: &str) -> Option<u32> {
+    major_version_from_version_string(contents.trim())
+}
+
+/// Parses a `.tool-versions` `java <distribution>-<version>` line, e.g.
+/// `java corretto-21.0.2` or `java temurin-17.0.9+9`, into a major version.
+fn parse_tool_versions_file(contents: &str) -> Option<u32> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != "java" {
+            return None;
+        }
+        let (_distribution, version) = parts.next()?.split_once('-')?;
+        major_version_from_version_string(version)
+    })
+}
+
+/// Extracts the major version from a JDK version string. Handles the
+/// legacy `1.x` scheme (`1.8` -> `8`) as well as modern single numbers
+/// (`21`, `21.0.2`, `17.0.9+9`).
+fn major_version_from_version_string(version: &str) -> Option<u32> {
+    let version = version.split(['+', '-']).next()?;
+    let mut components = version.split('.');
+    let first: u32 = components.next()?.parse().ok()?;
+    if first == 1 {
+        components.next()?.parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+/// Honors `JAVA_HOME` first, then scans the locations each OS conventionally
+/// installs JDKs in, returning the `java` binary of the first one found whose
+/// major version is at least `minimum_major`.
+///
+/// This is used to pick the JVM that *launches JDT-LS itself*, which is why
+/// it takes a minimum runtime version rather than the project's requested
+/// version: JDT-LS needs a modern JVM to execute regardless of what version
+/// the project being edited targets.
+fn discover_system_jdk(minimum_major: u32) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Ok(java_home) = std::env::var("JAVA_HOME") {
+        candidates.push(PathBuf::from(java_home));
+    }
+    candidates.extend(common_jdk_install_roots());
+
+    candidates.into_iter().find_map(|home| {
+        let java_bin = java_bin_in_home(&home);
+        if !java_bin.exists() {
+            return None;
+        }
+        match query_java_major_version(&java_bin) {
+            Some(major) if major >= minimum_major => Some(java_bin),
+            _ => None,
+        }
+    })
 }
 
-fn arguments(container_dir: &PathBuf) -> Vec<OsString> {
-    let jar = container_dir.join("plugins/org.eclipse.equinox.launcher_1.6.700.v20231214-2017.jar");
+fn java_bin_in_home(home: &Path) -> PathBuf {
+    match OS {
+        "windows" => home.join("bin/java.exe"),
+        _ => home.join("bin/java"),
+    }
+}
+
+/// Directories each OS's common JDK installers drop JDKs into.
+fn common_jdk_install_roots() -> Vec<PathBuf> {
+    let parent = match OS {
+        "macos" => Path::new("/Library/Java/JavaVirtualMachines"),
+        "linux" => Path::new("/usr/lib/jvm"),
+        "windows" => Path::new("C:\\Program Files\\Java"),
+        _ => return Vec::new(),
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            if OS == "macos" {
+                path.join("Contents/Home")
+            } else {
+                path
+            }
+        })
+        .collect()
+}
+
+/// Runs `java -version` and parses the major version out of its output,
+/// e.g. `openjdk version "21.0.2" 2024-01-16` or `java version "1.8.0_392"`.
+fn query_java_major_version(java_bin: &Path) -> Option<u32> {
+    let output = std::process::Command::new(java_bin)
+        .arg("-version")
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stderr);
+    let version_re = Regex::new(r#"version "([^"]+)""#).ok()?;
+    let version = version_re.captures(&output)?.get(1)?.as_str();
+    major_version_from_version_string(version)
+}
+
+/// JDT-LS's `-data` directory holds per-workspace metadata (the compiled
+/// workspace index, import state, etc.) and two workspaces must not share
+/// one, or their metadata clobbers each other. Keys the directory by a hash
+/// of the workspace root so each project under this `container_dir` gets
+/// its own.
+fn workspace_data_dir(container_dir: &Path, workspace_root: &Path) -> PathBuf {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    workspace_root.hash(&mut hasher);
+    container_dir.join(format!("data_{:x}", hasher.finish()))
+}
+
+/// Finds the top-level directory that the downloaded JDK archive extracted
+/// into (its name varies by version and platform).
+fn jdk_root(container_dir: &Path, distribution: JdkDistribution) -> Option<PathBuf> {
+    let prefix = distribution.home_dir_prefix();
+    std::fs::read_dir(container_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with(prefix))
+        })
+}
+
+fn unpack_zip(archive_path: &Path, dest: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(archive_path)?)?;
+    archive.extract(dest)?;
+    Ok(())
+}
+
+/// Corretto publishes the checksum for each `latest/...` artifact at the
+/// same path under `latest_checksum/`.
+fn checksum_url_for(download_url: &str) -> String {
+    download_url.replacen("/latest/", "/latest_checksum/", 1)
+}
+
+/// Parses a `sha256sum`-style checksum file (`<hex digest>  <filename>`),
+/// returning just the digest.
+fn parse_sha256_file(contents: &str) -> Option<&str> {
+    contents.split_whitespace().next()
+}
+
+/// Adoptium's `v3/assets/latest/{feature_version}/hotspot` API reports the
+/// single newest GA asset per os/arch/image_type for a feature version,
+/// including its checksum. This is the same build `v3/binary/latest/...`
+/// (used for the actual download) redirects to; `v3/assets/feature_releases`
+/// is NOT used here because it can list more than one GA release for a
+/// feature version (maintenance updates) with no guaranteed ordering, which
+/// risks pinning the checksum to a different build than the one downloaded.
+fn temurin_assets_api_url(feature_version: u32) -> String {
+    format!("https://api.adoptium.net/v3/assets/latest/{feature_version}/hotspot")
+}
+
+/// Pulls the checksum of the JDK binary matching `os`/`arch` out of an
+/// Adoptium `v3/assets/latest/{feature_version}/hotspot` JSON response.
+fn parse_temurin_assets_checksum(contents: &str, os: &str, arch: &str) -> Option<String> {
+    let assets: serde_json::Value = serde_json::from_str(contents).ok()?;
+    assets.as_array()?.iter().find_map(|asset| {
+        let binary = asset.get("binary")?;
+        if binary.get("image_type")?.as_str()? != "jdk" {
+            return None;
+        }
+        if binary.get("os")?.as_str()? != os || binary.get("architecture")?.as_str()? != arch {
+            return None;
+        }
+        binary
+            .get("package")?
+            .get("checksum")?
+            .as_str()
+            .map(|checksum| checksum.to_owned())
+    })
+}
+
+fn verify_checksum(actual_hex: &str, expected_hex: &str) -> Result<()> {
+    if actual_hex.eq_ignore_ascii_case(expected_hex.trim()) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "checksum mismatch: expected {}, got {}",
+            expected_hex,
+            actual_hex
+        ))
+    }
+}
+
+/// Downloads `url`'s body straight to `dest`, hashing it as it streams
+/// rather than buffering the whole archive (easily hundreds of MB for a
+/// JDK or JDT-LS build) into memory first. Deletes `dest` again if the
+/// download doesn't match `expected_sha256`.
+async fn download_to_file(
+    delegate: &dyn LspAdapterDelegate,
+    url: &str,
+    dest: &Path,
+    expected_sha256: Option<&str>,
+) -> Result<()> {
+    let mut response = delegate
+        .http_client()
+        .get(url, Default::default(), true)
+        .await
+        .map_err(|err| anyhow!("error downloading {}: {}", url, err))?;
+
+    let mut file = std::fs::File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0_u8; 64 * 1024];
+    loop {
+        let read = response.body_mut().read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        file.write_all(&buf[..read])?;
+    }
+    drop(file);
+
+    if let Some(expected_sha256) = expected_sha256 {
+        let actual_sha256 = format!("{:x}", hasher.finalize());
+        if let Err(err) = verify_checksum(&actual_sha256, expected_sha256) {
+            std::fs::remove_file(dest).ok();
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+fn java(container_dir: &PathBuf, distribution: JdkDistribution) -> PathBuf {
+    let Some(root) = jdk_root(container_dir, distribution) else {
+        return PathBuf::new();
+    };
+    match OS {
+        "macos" => root.join("Contents/Home/bin/java"),
+        "windows" => root.join("bin/java.exe"),
+        _ => root.join("bin/java"),
+    }
+}
+
+fn arguments(container_dir: &PathBuf, data_dir: &Path) -> Vec<OsString> {
+    let jar = find_equinox_launcher_jar(container_dir)
+        .unwrap_or_else(|| container_dir.join("plugins/org.eclipse.equinox.launcher.jar"));
     let jar = jar.to_str().unwrap().trim();
+    let data_dir = data_dir.to_str().unwrap();
 
     vec![
         "-jar",
@@ -199,21 +975,302 @@ fn arguments(container_dir: &PathBuf) -> Vec<OsString> {
         "-configuration",
         &config(container_dir),
         "-data",
-        ".", // here JDT wants the project dir, but does Zed provide it and is it necessary?
+        data_dir,
     ]
     .into_iter()
     .map(OsString::from)
     .collect()
 }
 
+/// Finds the highest-versioned `org.eclipse.equinox.launcher_*.jar` in the
+/// extracted JDT-LS build. Its version segment changes with every JDT-LS
+/// release, so it can't be hardcoded.
+fn find_equinox_launcher_jar(container_dir: &Path) -> Option<PathBuf> {
+    let jar_re =
+        Regex::new(r"^org\.eclipse\.equinox\.launcher_(\d+)\.(\d+)\.(\d+)\..*\.jar$").ok()?;
+    std::fs::read_dir(container_dir.join("plugins"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let captures = jar_re.captures(file_name.to_str()?)?;
+            let version: (u32, u32, u32) = (
+                captures[1].parse().ok()?,
+                captures[2].parse().ok()?,
+                captures[3].parse().ok()?,
+            );
+            Some((version, entry.path()))
+        })
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, path)| path)
+}
+
+/// Resolves the JDT-LS `-configuration` directory by probing for the
+/// `config_{mac,mac_arm,linux,win}` folder the extracted build actually
+/// contains, rather than assuming one from `(os, arch)`.
 fn config(container_dir: &PathBuf) -> String {
-    match ARCH {
-        "aarch64" => container_dir.join("config_mac").to_str().unwrap().into(),
-        "x86_64" => container_dir
-            .join("config_mac_arm")
-            .to_str()
-            .unwrap()
-            .into(),
-        _ => "".into(), // meh
+    let candidates: &[&str] = match OS {
+        "linux" => &["config_linux"],
+        "windows" => &["config_win"],
+        "macos" => match ARCH {
+            "aarch64" => &["config_mac_arm", "config_mac"],
+            _ => &["config_mac", "config_mac_arm"],
+        },
+        _ => &[],
+    };
+
+    for candidate in candidates {
+        let path = container_dir.join(candidate);
+        if path.exists() {
+            return path.to_str().unwrap().into();
+        }
+    }
+
+    find_any_config_dir(container_dir)
+        .map(|path| path.to_str().unwrap().into())
+        .unwrap_or_default()
+}
+
+fn find_any_config_dir(container_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(container_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with("config_"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zulu_home_dir_prefix_matches_its_actual_archive_layout() {
+        let prefix = JdkDistribution::Zulu.home_dir_prefix();
+        assert!("zulu21.32.17-ca-jdk21.0.2-linux_x64".starts_with(prefix));
+        assert!("zulu17.46.19-ca-jdk17.0.9-macosx_aarch64".starts_with(prefix));
+    }
+
+    #[test]
+    fn other_distributions_home_dir_prefixes_still_match() {
+        assert!("amazon-corretto-21.0.2.13.1-linux-x64"
+            .starts_with(JdkDistribution::Corretto.home_dir_prefix()));
+        assert!("jdk-21.0.2+13".starts_with(JdkDistribution::Temurin.home_dir_prefix()));
+        assert!("graalvm-community-openjdk-21.0.2+13.1"
+            .starts_with(JdkDistribution::GraalVm.home_dir_prefix()));
+    }
+
+    #[test]
+    fn jdk_distribution_setting_is_read_from_zed_settings_json() {
+        let settings = r#"{ "languages": { "Java": { "jdk_distribution": "temurin" } } }"#;
+        let defaults = ConfiguredJavaSettings::defaults_for(JdkDistribution::Corretto);
+        let resolved = parse_java_language_settings(settings, defaults.clone());
+        assert_eq!(resolved.jdk_distribution, JdkDistribution::Temurin);
+        assert!(resolved.jdk_distribution_explicit);
+    }
+
+    #[test]
+    fn missing_settings_fall_back_to_defaults() {
+        let settings = r#"{ "languages": { "Java": {} } }"#;
+        let defaults = ConfiguredJavaSettings::defaults_for(JdkDistribution::Zulu);
+        let resolved = parse_java_language_settings(settings, defaults.clone());
+        assert_eq!(resolved, defaults);
+    }
+
+    #[test]
+    fn all_java_settings_are_read_when_present() {
+        let settings = r#"{ "languages": { "Java": {
+            "jdk_distribution": "graalvm",
+            "import": { "gradle": false, "maven": false },
+            "format_on_save": false,
+            "null_analysis_mode": "disabled"
+        } } }"#;
+        let defaults = ConfiguredJavaSettings::defaults_for(JdkDistribution::Corretto);
+        let resolved = parse_java_language_settings(settings, defaults);
+        assert_eq!(
+            resolved,
+            ConfiguredJavaSettings {
+                jdk_distribution: JdkDistribution::GraalVm,
+                jdk_distribution_explicit: true,
+                gradle_enabled: false,
+                maven_enabled: false,
+                format_on_save: false,
+                null_analysis_mode: "disabled".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn temurin_assets_checksum_is_parsed_from_the_adoptium_api_response() {
+        let response = r#"[{
+            "binary": {
+                "os": "linux",
+                "architecture": "x64",
+                "image_type": "jdk",
+                "package": {
+                    "checksum": "abc123",
+                    "name": "OpenJDK21U-jdk_x64_linux_hotspot_21.0.2_13.tar.gz"
+                }
+            }
+        }]"#;
+        assert_eq!(
+            parse_temurin_assets_checksum(response, "linux", "x64"),
+            Some("abc123".to_owned())
+        );
+    }
+
+    #[test]
+    fn temurin_assets_checksum_skips_entries_for_other_os_and_arch() {
+        let response = r#"[
+            {
+                "binary": {
+                    "os": "mac",
+                    "architecture": "aarch64",
+                    "image_type": "jdk",
+                    "package": { "checksum": "mac-checksum", "name": "..." }
+                }
+            },
+            {
+                "binary": {
+                    "os": "linux",
+                    "architecture": "x64",
+                    "image_type": "jre",
+                    "package": { "checksum": "jre-checksum", "name": "..." }
+                }
+            },
+            {
+                "binary": {
+                    "os": "linux",
+                    "architecture": "x64",
+                    "image_type": "jdk",
+                    "package": { "checksum": "jdk-checksum", "name": "..." }
+                }
+            }
+        ]"#;
+        assert_eq!(
+            parse_temurin_assets_checksum(response, "linux", "x64"),
+            Some("jdk-checksum".to_owned())
+        );
+    }
+
+    #[test]
+    fn major_version_from_version_string_handles_legacy_and_modern_schemes() {
+        assert_eq!(major_version_from_version_string("1.8"), Some(8));
+        assert_eq!(major_version_from_version_string("21"), Some(21));
+        assert_eq!(major_version_from_version_string("21.0.2"), Some(21));
+        assert_eq!(major_version_from_version_string("17.0.9+9"), Some(17));
+        assert_eq!(major_version_from_version_string(""), None);
+        assert_eq!(major_version_from_version_string("not-a-version"), None);
+    }
+
+    #[test]
+    fn java_version_file_is_parsed_into_a_major_version() {
+        assert_eq!(parse_java_version_file("21\n"), Some(21));
+        assert_eq!(parse_java_version_file("1.8"), Some(8));
+    }
+
+    #[test]
+    fn tool_versions_file_finds_the_java_line_and_parses_its_version() {
+        let contents = "nodejs 20.11.0\njava corretto-21.0.2\npython 3.12.1\n";
+        assert_eq!(parse_tool_versions_file(contents), Some(21));
+    }
+
+    #[test]
+    fn tool_versions_file_parses_a_legacy_style_java_version() {
+        assert_eq!(parse_tool_versions_file("java temurin-1.8.0"), Some(8));
+    }
+
+    #[test]
+    fn tool_versions_file_without_a_java_line_returns_none() {
+        let contents = "nodejs 20.11.0\npython 3.12.1\n";
+        assert_eq!(parse_tool_versions_file(contents), None);
+    }
+
+    #[test]
+    fn tool_versions_file_with_a_malformed_java_line_returns_none() {
+        assert_eq!(parse_tool_versions_file("java corretto"), None);
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "zed-java-lsp-test-{}-{}-{}",
+            name,
+            std::process::id(),
+            name.len()
+        ));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn equinox_launcher_jar_picks_the_highest_version_when_several_are_present() {
+        let container_dir = scratch_dir("equinox-launcher");
+        let plugins_dir = container_dir.join("plugins");
+        std::fs::create_dir_all(&plugins_dir).unwrap();
+        for name in [
+            "org.eclipse.equinox.launcher_1.6.400.v20210924-0641.jar",
+            "org.eclipse.equinox.launcher_1.6.500.v20230214-1058.jar",
+            "org.eclipse.equinox.launcher_1.6.100.v20201223-0821.jar",
+            "not-a-launcher.jar",
+        ] {
+            std::fs::write(plugins_dir.join(name), b"").unwrap();
+        }
+
+        let found = find_equinox_launcher_jar(&container_dir).unwrap();
+        assert_eq!(
+            found.file_name().unwrap().to_str().unwrap(),
+            "org.eclipse.equinox.launcher_1.6.500.v20230214-1058.jar"
+        );
+
+        std::fs::remove_dir_all(&container_dir).ok();
+    }
+
+    #[test]
+    fn equinox_launcher_jar_is_none_when_plugins_dir_is_missing() {
+        let container_dir = scratch_dir("equinox-launcher-missing");
+        assert!(find_equinox_launcher_jar(&container_dir).is_none());
+        std::fs::remove_dir_all(&container_dir).ok();
+    }
+
+    #[test]
+    fn find_any_config_dir_finds_a_config_prefixed_directory() {
+        let container_dir = scratch_dir("any-config-dir");
+        std::fs::create_dir_all(container_dir.join("config_linux")).unwrap();
+        std::fs::create_dir_all(container_dir.join("plugins")).unwrap();
+
+        let found = find_any_config_dir(&container_dir).unwrap();
+        assert_eq!(found.file_name().unwrap().to_str().unwrap(), "config_linux");
+
+        std::fs::remove_dir_all(&container_dir).ok();
+    }
+
+    #[test]
+    fn find_any_config_dir_is_none_when_nothing_matches() {
+        let container_dir = scratch_dir("any-config-dir-missing");
+        std::fs::create_dir_all(container_dir.join("plugins")).unwrap();
+
+        assert!(find_any_config_dir(&container_dir).is_none());
+
+        std::fs::remove_dir_all(&container_dir).ok();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn config_falls_back_to_any_config_dir_when_the_os_specific_one_is_missing() {
+        let container_dir = scratch_dir("config-fallback");
+        std::fs::create_dir_all(container_dir.join("config_weird_arch")).unwrap();
+
+        assert_eq!(
+            config(&container_dir),
+            container_dir.join("config_weird_arch").to_str().unwrap()
+        );
+
+        std::fs::remove_dir_all(&container_dir).ok();
     }
 }